@@ -0,0 +1,391 @@
+use std::{str::FromStr, sync::Arc, time::Duration};
+
+use openssl::ssl::{SslConnector, SslMethod};
+use rustls::{ClientConfig, RootCertStore};
+use tokio::{net::TcpStream, time::timeout};
+use tokio_native_tls::{native_tls, TlsConnector as NativeTlsConnector};
+use tokio_openssl::SslStream;
+use tokio_rustls::{rustls, rustls::pki_types::ServerName, TlsConnector as RustlsConnector};
+
+/// Which TLS stack to use for a connection attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    OpenSsl,
+    NativeTls,
+    Rustls,
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "openssl" => Ok(Backend::OpenSsl),
+            "native-tls" => Ok(Backend::NativeTls),
+            "rustls" => Ok(Backend::Rustls),
+            other => Err(format!("unknown backend: {other}")),
+        }
+    }
+}
+
+/// The structured result of a single connection + TLS handshake attempt,
+/// replacing the old "println if ok, eprintln a magic error code" approach.
+#[derive(Debug)]
+pub enum ConnectOutcome {
+    /// Handshake succeeded. `leaf_cert_der` is the DER encoding of the peer's
+    /// leaf certificate, when the backend was able to recover it from the
+    /// completed session.
+    Ok {
+        leaf_cert_der: Option<Vec<u8>>,
+    },
+    CertInvalid(String),
+    HandshakeReset,
+    /// A `HandshakeReset` that `--classify` has attributed to either SNI
+    /// filtering or an IP-level block, by varying the SNI sent to the same
+    /// address.
+    Blocked(BlockKind),
+    ConnectRefused(String),
+    Timeout,
+    /// DNS resolution for the domain failed outright, so no connection was
+    /// even attempted.
+    DnsFail(String),
+    /// The leaf certificate presented didn't match any applicable TLSA
+    /// record published for the domain.
+    DaneFail,
+}
+
+/// Attempt to connect to `addr:443` (a hostname or literal IP) and complete a
+/// TLS handshake sending `sni` as the TLS server name, with separate
+/// timeouts for the TCP connect and the TLS handshake. Callers resolve the
+/// host themselves and pass the chosen address in as `addr`, so the
+/// handshake - and anything derived from its result, like a DANE/TLSA check
+/// - is guaranteed to be about the same server that was resolved, rather
+/// than letting the TCP connect re-resolve the host independently.
+pub async fn check_domain_at(
+    backend: Backend,
+    addr: &str,
+    sni: &str,
+    connect_timeout: Duration,
+    handshake_timeout: Duration,
+) -> ConnectOutcome {
+    match backend {
+        Backend::OpenSsl => check_openssl(addr, sni, connect_timeout, handshake_timeout).await,
+        Backend::NativeTls => check_native_tls(addr, sni, connect_timeout, handshake_timeout).await,
+        Backend::Rustls => check_rustls(addr, sni, connect_timeout, handshake_timeout).await,
+    }
+}
+
+/// Which party a reset handshake was most likely blocked by, as
+/// distinguished by varying only the SNI sent to the same IP.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockKind {
+    Sni,
+    Ip,
+}
+
+/// A non-sensitive control hostname sent as SNI when probing whether a
+/// blocked domain is blocked by SNI filtering or at the IP level.
+const BENIGN_SNI: &str = "www.example.com";
+
+/// Re-probe `addr` twice - once with the domain's real SNI, once with a
+/// benign control SNI - to tell an SNI-triggered block apart from one that
+/// blocks the IP outright. Meant to be called after an initial
+/// `HandshakeReset` from [`check_domain_at`], with `addr` being the exact
+/// same address that attempt connected to - a literal IP, not a hostname
+/// `--classify` would have to re-resolve and could land on a different
+/// server with.
+pub async fn classify_reset(
+    backend: Backend,
+    addr: &str,
+    real_sni: &str,
+    connect_timeout: Duration,
+    handshake_timeout: Duration,
+) -> BlockKind {
+    // Both probes must hit the same edge the initial attempt did; an `addr`
+    // that isn't a literal IP can't guarantee that, so there's nothing
+    // trustworthy to classify.
+    if addr.parse::<std::net::IpAddr>().is_err() {
+        return BlockKind::Ip;
+    }
+
+    let real = check_domain_at(backend, addr, real_sni, connect_timeout, handshake_timeout).await;
+    let benign = check_domain_at(
+        backend,
+        addr,
+        BENIGN_SNI,
+        connect_timeout,
+        handshake_timeout,
+    )
+    .await;
+
+    classify(&real, &benign)
+}
+
+/// The decision table behind `classify_reset`, split out so it can be
+/// exercised without a network round trip. Only a clean benign-SNI
+/// handshake is evidence the IP itself is reachable; a `CertInvalid`
+/// (hostname/SAN mismatch against the real cert), `Timeout`, or
+/// `ConnectRefused` on the benign probe is not proof the block is
+/// SNI-specific, so anything short of `Ok` is treated the same as an
+/// IP-level block.
+fn classify(real: &ConnectOutcome, benign: &ConnectOutcome) -> BlockKind {
+    match (real, benign) {
+        (ConnectOutcome::HandshakeReset, ConnectOutcome::Ok { .. }) => BlockKind::Sni,
+        _ => BlockKind::Ip,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn benign_ok_means_sni_blocked() {
+        let real = ConnectOutcome::HandshakeReset;
+        let benign = ConnectOutcome::Ok {
+            leaf_cert_der: None,
+        };
+        assert_eq!(classify(&real, &benign), BlockKind::Sni);
+    }
+
+    #[test]
+    fn both_reset_means_ip_blocked() {
+        let real = ConnectOutcome::HandshakeReset;
+        let benign = ConnectOutcome::HandshakeReset;
+        assert_eq!(classify(&real, &benign), BlockKind::Ip);
+    }
+
+    #[test]
+    fn benign_cert_invalid_is_inconclusive_and_treated_as_ip() {
+        // A hostname/SAN mismatch on the benign probe (the common case,
+        // since the cert belongs to the real domain, not the benign SNI)
+        // is not proof of a clean handshake and must not be read as "Sni".
+        let real = ConnectOutcome::HandshakeReset;
+        let benign = ConnectOutcome::CertInvalid("hostname mismatch".to_string());
+        assert_eq!(classify(&real, &benign), BlockKind::Ip);
+    }
+
+    #[test]
+    fn benign_timeout_is_inconclusive_and_treated_as_ip() {
+        let real = ConnectOutcome::HandshakeReset;
+        let benign = ConnectOutcome::Timeout;
+        assert_eq!(classify(&real, &benign), BlockKind::Ip);
+    }
+
+    #[tokio::test]
+    async fn classify_reset_rejects_a_hostname_instead_of_a_pinned_ip() {
+        // A hostname would have to be re-resolved by the probes themselves,
+        // defeating the whole point of pinning the address; bail out before
+        // any network I/O rather than risk classifying a different server.
+        let kind = classify_reset(
+            Backend::OpenSsl,
+            "example.com",
+            "example.com",
+            Duration::from_secs(1),
+            Duration::from_secs(1),
+        )
+        .await;
+        assert_eq!(kind, BlockKind::Ip);
+    }
+
+    #[test]
+    fn bad_certificate_alert_is_cert_invalid() {
+        assert!(is_cert_alert(rustls::AlertDescription::BadCertificate));
+        assert!(is_cert_alert(rustls::AlertDescription::CertificateExpired));
+        assert!(is_cert_alert(rustls::AlertDescription::UnknownCA));
+    }
+
+    #[test]
+    fn non_cert_alerts_are_not_cert_invalid() {
+        assert!(!is_cert_alert(rustls::AlertDescription::HandshakeFailure));
+        assert!(!is_cert_alert(rustls::AlertDescription::AccessDenied));
+        assert!(!is_cert_alert(rustls::AlertDescription::ProtocolVersion));
+    }
+}
+
+async fn connect_tcp(addr: &str, connect_timeout: Duration) -> Result<TcpStream, ConnectOutcome> {
+    let addr = format!("{addr}:443");
+    match timeout(connect_timeout, TcpStream::connect(&addr)).await {
+        Ok(Ok(stream)) => Ok(stream),
+        Ok(Err(err)) => Err(ConnectOutcome::ConnectRefused(err.to_string())),
+        Err(_) => Err(ConnectOutcome::Timeout),
+    }
+}
+
+async fn check_openssl(
+    addr: &str,
+    sni: &str,
+    connect_timeout: Duration,
+    handshake_timeout: Duration,
+) -> ConnectOutcome {
+    let stream = match connect_tcp(addr, connect_timeout).await {
+        Ok(stream) => stream,
+        Err(outcome) => return outcome,
+    };
+
+    let connector = match SslConnector::builder(SslMethod::tls_client()) {
+        Ok(builder) => builder.build(),
+        Err(err) => return ConnectOutcome::ConnectRefused(err.to_string()),
+    };
+    let ssl = match connector.configure().and_then(|c| c.into_ssl(sni)) {
+        Ok(ssl) => ssl,
+        Err(err) => return ConnectOutcome::ConnectRefused(err.to_string()),
+    };
+    let mut ssl_stream = match SslStream::new(ssl, stream) {
+        Ok(ssl_stream) => ssl_stream,
+        Err(err) => return ConnectOutcome::ConnectRefused(err.to_string()),
+    };
+
+    match timeout(
+        handshake_timeout,
+        std::pin::Pin::new(&mut ssl_stream).connect(),
+    )
+    .await
+    {
+        Ok(Ok(())) => {
+            let leaf_cert_der = ssl_stream
+                .ssl()
+                .peer_certificate()
+                .and_then(|c| c.to_der().ok());
+            ConnectOutcome::Ok { leaf_cert_der }
+        }
+        // code 5 is SSL_ERROR_SYSCALL, which is what a mid-handshake RST looks
+        // like through openssl's error reporting.
+        Ok(Err(err)) if err.code().as_raw() == 5 => ConnectOutcome::HandshakeReset,
+        Ok(Err(err)) => ConnectOutcome::CertInvalid(err.to_string()),
+        Err(_) => ConnectOutcome::Timeout,
+    }
+}
+
+async fn check_native_tls(
+    addr: &str,
+    sni: &str,
+    connect_timeout: Duration,
+    handshake_timeout: Duration,
+) -> ConnectOutcome {
+    let stream = match connect_tcp(addr, connect_timeout).await {
+        Ok(stream) => stream,
+        Err(outcome) => return outcome,
+    };
+
+    let connector = match native_tls::TlsConnector::new() {
+        Ok(connector) => NativeTlsConnector::from(connector),
+        Err(err) => return ConnectOutcome::ConnectRefused(err.to_string()),
+    };
+
+    match timeout(handshake_timeout, connector.connect(sni, stream)).await {
+        Ok(Ok(tls_stream)) => {
+            let leaf_cert_der = tls_stream
+                .get_ref()
+                .peer_certificate()
+                .ok()
+                .flatten()
+                .and_then(|cert| cert.to_der().ok());
+            ConnectOutcome::Ok { leaf_cert_der }
+        }
+        Ok(Err(err)) => {
+            let msg = err.to_string();
+            if msg.contains("EOF") || msg.contains("reset") {
+                ConnectOutcome::HandshakeReset
+            } else {
+                ConnectOutcome::CertInvalid(msg)
+            }
+        }
+        Err(_) => ConnectOutcome::Timeout,
+    }
+}
+
+async fn check_rustls(
+    addr: &str,
+    sni: &str,
+    connect_timeout: Duration,
+    handshake_timeout: Duration,
+) -> ConnectOutcome {
+    let stream = match connect_tcp(addr, connect_timeout).await {
+        Ok(stream) => stream,
+        Err(outcome) => return outcome,
+    };
+
+    let mut root_store = RootCertStore::empty();
+    root_store.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    let config = ClientConfig::builder()
+        .with_root_certificates(root_store)
+        .with_no_client_auth();
+    let connector = RustlsConnector::from(Arc::new(config));
+
+    let server_name = match ServerName::try_from(sni.to_string()) {
+        Ok(name) => name,
+        Err(err) => return ConnectOutcome::ConnectRefused(err.to_string()),
+    };
+
+    match timeout(handshake_timeout, connector.connect(server_name, stream)).await {
+        Ok(Ok(tls_stream)) => {
+            let leaf_cert_der = tls_stream
+                .get_ref()
+                .1
+                .peer_certificates()
+                .and_then(|certs| certs.first())
+                .map(|cert| cert.as_ref().to_vec());
+            ConnectOutcome::Ok { leaf_cert_der }
+        }
+        Ok(Err(err)) => classify_rustls_error(&err),
+        Err(_) => ConnectOutcome::Timeout,
+    }
+}
+
+/// rustls reports a mid-handshake reset as an unexpected EOF (or, for a TCP
+/// RST, a connection reset straight from the socket) and a bad cert
+/// as `InvalidCertificate`/`AlertReceived`, so unlike the openssl backend's
+/// single magic error code, these come back as distinct `io::Error` kinds.
+fn classify_rustls_error(err: &std::io::Error) -> ConnectOutcome {
+    if let Some(rustls_err) = err
+        .get_ref()
+        .and_then(|e| e.downcast_ref::<rustls::Error>())
+    {
+        return match rustls_err {
+            rustls::Error::InvalidCertificate(reason) => {
+                ConnectOutcome::CertInvalid(format!("{reason:?}"))
+            }
+            rustls::Error::AlertReceived(alert) if is_cert_alert(*alert) => {
+                ConnectOutcome::CertInvalid(format!("alert: {alert:?}"))
+            }
+            // A non-cert alert (handshake_failure, access_denied,
+            // protocol_version, ...) tearing down the connection is itself a
+            // block signal, not a cert problem - keep it as a reset rather
+            // than conflating the two.
+            rustls::Error::AlertReceived(_) => ConnectOutcome::HandshakeReset,
+            other => ConnectOutcome::CertInvalid(other.to_string()),
+        };
+    }
+
+    if matches!(
+        err.kind(),
+        std::io::ErrorKind::UnexpectedEof | std::io::ErrorKind::ConnectionReset
+    ) {
+        return ConnectOutcome::HandshakeReset;
+    }
+
+    ConnectOutcome::ConnectRefused(err.to_string())
+}
+
+/// Whether a TLS alert indicates a problem with the certificate itself, as
+/// opposed to a handshake torn down for some other reason (policy,
+/// protocol mismatch, etc.) that looks just as block-like as a reset.
+fn is_cert_alert(alert: rustls::AlertDescription) -> bool {
+    use rustls::AlertDescription::*;
+
+    matches!(
+        alert,
+        BadCertificate
+            | UnsupportedCertificate
+            | CertificateRevoked
+            | CertificateExpired
+            | CertificateUnknown
+            | UnknownCA
+            | CertificateUnobtainable
+            | BadCertificateStatusResponse
+            | BadCertificateHashValue
+            | CertificateRequired
+            | NoCertificate
+    )
+}
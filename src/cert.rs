@@ -0,0 +1,163 @@
+use openssl::{asn1::Asn1Time, nid::Nid, x509::X509};
+
+/// Everything we learned about the leaf certificate presented during a
+/// successful handshake.
+#[derive(Debug, Clone)]
+pub struct CertInfo {
+    pub not_before: String,
+    pub not_after: String,
+    pub issuer_cn: Option<String>,
+    pub sans: Vec<String>,
+    pub sni_matches_san: bool,
+}
+
+/// A non-fatal condition worth surfacing about an otherwise valid cert.
+#[derive(Debug, Clone)]
+pub enum CertWarning {
+    ExpiringSoon {
+        days_left: i32,
+    },
+    /// The SNI host we connected with isn't covered by the cert's identity.
+    /// Every backend verifies the hostname during the handshake, so a
+    /// completed handshake normally already implies a match; this fires only
+    /// where the SAN/CN matching here disagrees with the backend's. Only the
+    /// initial handshake's cert is inspected, never `--classify`'s probes.
+    SanMismatch,
+}
+
+/// Parse a leaf certificate (DER-encoded, as handed back by whichever TLS
+/// backend completed the handshake) and report its expiry, issuer, SANs and
+/// whether the SNI host we connected with is actually covered by one of
+/// them.
+pub fn inspect(
+    der: &[u8],
+    sni_host: &str,
+    expiry_warn_days: i32,
+) -> anyhow::Result<(CertInfo, Vec<CertWarning>)> {
+    let cert = X509::from_der(der)?;
+
+    let not_before = cert.not_before().to_string();
+    let not_after = cert.not_after().to_string();
+
+    let issuer_cn = cert
+        .issuer_name()
+        .entries_by_nid(Nid::COMMONNAME)
+        .next()
+        .and_then(|entry| entry.data().as_utf8().ok())
+        .map(|s| s.to_string());
+
+    let sans = cert
+        .subject_alt_names()
+        .map(|names| {
+            names
+                .iter()
+                .filter_map(|name| name.dnsname().map(str::to_string))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    // Modern hostname verification only trusts the SAN extension, but plenty
+    // of legacy certs carry no SAN at all and rely on the subject's CN
+    // instead, so treat that as the fallback rather than silently skipping
+    // the mismatch check whenever SANs are absent.
+    let sni_matches_san = if sans.is_empty() {
+        cert.subject_name()
+            .entries_by_nid(Nid::COMMONNAME)
+            .next()
+            .and_then(|entry| entry.data().as_utf8().ok())
+            .is_some_and(|cn| san_matches(&cn, sni_host))
+    } else {
+        sans.iter().any(|san| san_matches(san, sni_host))
+    };
+
+    let mut warnings = Vec::new();
+    if !sni_matches_san {
+        warnings.push(CertWarning::SanMismatch);
+    }
+
+    if let Ok(warn_threshold) = Asn1Time::days_from_now(expiry_warn_days.max(0) as u32) {
+        if cert.not_after() <= &*warn_threshold {
+            if let Ok(diff) = Asn1Time::days_from_now(0)?.diff(cert.not_after()) {
+                warnings.push(CertWarning::ExpiringSoon {
+                    days_left: diff.days,
+                });
+            }
+        }
+    }
+
+    Ok((
+        CertInfo {
+            not_before,
+            not_after,
+            issuer_cn,
+            sans,
+            sni_matches_san,
+        },
+        warnings,
+    ))
+}
+
+/// Match a SAN entry against the host we sent as SNI, allowing a single
+/// leading `*.` wildcard label as every CA issues them.
+fn san_matches(san: &str, host: &str) -> bool {
+    let san = san.to_ascii_lowercase();
+    let host = host.to_ascii_lowercase();
+
+    if let Some(suffix) = san.strip_prefix("*.") {
+        host.split_once('.').is_some_and(|(_, rest)| rest == suffix)
+    } else {
+        san == host
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::TEST_CERT_DER as LEGACY_CN_ONLY_CERT_DER;
+
+    #[test]
+    fn exact_match() {
+        assert!(san_matches("example.com", "example.com"));
+    }
+
+    #[test]
+    fn case_insensitive_match() {
+        assert!(san_matches("Example.COM", "example.com"));
+    }
+
+    #[test]
+    fn wildcard_matches_single_label() {
+        assert!(san_matches("*.example.com", "www.example.com"));
+    }
+
+    #[test]
+    fn wildcard_does_not_match_bare_apex() {
+        assert!(!san_matches("*.example.com", "example.com"));
+    }
+
+    #[test]
+    fn wildcard_does_not_match_multiple_labels() {
+        assert!(!san_matches("*.example.com", "a.b.example.com"));
+    }
+
+    #[test]
+    fn different_domains_do_not_match() {
+        assert!(!san_matches("example.com", "example.net"));
+    }
+
+    #[test]
+    fn legacy_cn_only_cert_matches_its_own_hostname() {
+        let (_, warnings) = inspect(LEGACY_CN_ONLY_CERT_DER, "example.com", 30).unwrap();
+        assert!(!warnings
+            .iter()
+            .any(|w| matches!(w, CertWarning::SanMismatch)));
+    }
+
+    #[test]
+    fn legacy_cn_only_cert_flags_mismatch_for_other_hostnames() {
+        let (_, warnings) = inspect(LEGACY_CN_ONLY_CERT_DER, "other.example", 30).unwrap();
+        assert!(warnings
+            .iter()
+            .any(|w| matches!(w, CertWarning::SanMismatch)));
+    }
+}
@@ -0,0 +1,126 @@
+use openssl::{hash::MessageDigest, x509::X509};
+
+use crate::dns::TlsaRecord;
+
+/// Result of comparing a handshake's leaf certificate against any TLSA
+/// records published for the domain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DaneOutcome {
+    /// No TLSA records for cert-usage PKIX-EE (1) or DANE-EE (3) were
+    /// published, so there's nothing to pin against.
+    NotApplicable,
+    Verified,
+    Fail,
+}
+
+/// Verify `leaf_cert_der` against the DANE-EE (3) / PKIX-EE (1) TLSA records
+/// in `records`, per RFC 6698. CA-constraint usages (0, 2) aren't checked
+/// here since they pin an issuer in the chain, not the leaf.
+pub fn verify(records: &[TlsaRecord], leaf_cert_der: &[u8]) -> DaneOutcome {
+    let applicable = records
+        .iter()
+        .filter(|r| r.cert_usage == 1 || r.cert_usage == 3);
+
+    let mut seen_applicable = false;
+    for record in applicable {
+        seen_applicable = true;
+        if matches(record, leaf_cert_der) {
+            return DaneOutcome::Verified;
+        }
+    }
+
+    if seen_applicable {
+        DaneOutcome::Fail
+    } else {
+        DaneOutcome::NotApplicable
+    }
+}
+
+fn matches(record: &TlsaRecord, leaf_cert_der: &[u8]) -> bool {
+    let selected = match record.selector {
+        // selector 0: full certificate
+        0 => leaf_cert_der.to_vec(),
+        // selector 1: SubjectPublicKeyInfo only
+        1 => {
+            let Ok(cert) = X509::from_der(leaf_cert_der) else {
+                return false;
+            };
+            let Ok(pubkey) = cert.public_key() else {
+                return false;
+            };
+            let Ok(spki) = pubkey.public_key_to_der() else {
+                return false;
+            };
+            spki
+        }
+        _ => return false,
+    };
+
+    let candidate = match record.matching_type {
+        0 => selected,
+        1 => match openssl::hash::hash(MessageDigest::sha256(), &selected) {
+            Ok(digest) => digest.to_vec(),
+            Err(_) => return false,
+        },
+        2 => match openssl::hash::hash(MessageDigest::sha512(), &selected) {
+            Ok(digest) => digest.to_vec(),
+            Err(_) => return false,
+        },
+        _ => return false,
+    };
+
+    candidate == record.association_data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::{TEST_CERT_DER, TEST_CERT_SHA256, TEST_SPKI_SHA256};
+
+    fn record(selector: u8, matching_type: u8, association_data: Vec<u8>) -> TlsaRecord {
+        TlsaRecord {
+            cert_usage: 3,
+            selector,
+            matching_type,
+            association_data,
+        }
+    }
+
+    #[test]
+    fn selector_0_matching_type_0_exact_cert_match() {
+        let rec = record(0, 0, TEST_CERT_DER.to_vec());
+        assert!(matches(&rec, TEST_CERT_DER));
+    }
+
+    #[test]
+    fn selector_0_matching_type_1_sha256_of_full_cert() {
+        let rec = record(0, 1, TEST_CERT_SHA256.to_vec());
+        assert!(matches(&rec, TEST_CERT_DER));
+    }
+
+    #[test]
+    fn selector_1_matching_type_1_sha256_of_spki() {
+        let rec = record(1, 1, TEST_SPKI_SHA256.to_vec());
+        assert!(matches(&rec, TEST_CERT_DER));
+    }
+
+    #[test]
+    fn mismatched_digest_does_not_match() {
+        let mut wrong = TEST_CERT_SHA256;
+        wrong[0] ^= 0xff;
+        let rec = record(0, 1, wrong.to_vec());
+        assert!(!matches(&rec, TEST_CERT_DER));
+    }
+
+    #[test]
+    fn unknown_selector_does_not_match() {
+        let rec = record(9, 0, TEST_CERT_DER.to_vec());
+        assert!(!matches(&rec, TEST_CERT_DER));
+    }
+
+    #[test]
+    fn unknown_matching_type_does_not_match() {
+        let rec = record(0, 9, TEST_CERT_DER.to_vec());
+        assert!(!matches(&rec, TEST_CERT_DER));
+    }
+}
@@ -0,0 +1,118 @@
+use std::{
+    net::{Ipv4Addr, Ipv6Addr},
+    time::Duration,
+};
+
+use hickory_resolver::{
+    proto::rr::{rdata::tlsa::TLSA, RData, RecordType},
+    TokioAsyncResolver,
+};
+
+/// A CAA record, as published by the zone owner to restrict which CAs may
+/// issue certificates for the domain.
+#[derive(Debug, Clone)]
+pub struct CaaRecord {
+    pub critical: bool,
+    pub tag: String,
+    pub value: String,
+}
+
+/// A TLSA record, per RFC 6698: cert-usage, selector and matching-type
+/// together describe how `association_data` should be compared against the
+/// certificate presented on the wire.
+#[derive(Debug, Clone)]
+pub struct TlsaRecord {
+    pub cert_usage: u8,
+    pub selector: u8,
+    pub matching_type: u8,
+    pub association_data: Vec<u8>,
+}
+
+/// Everything resolved ahead of the TLS handshake for a single domain.
+#[derive(Debug, Clone, Default)]
+pub struct DnsRecords {
+    pub a: Vec<Ipv4Addr>,
+    pub aaaa: Vec<Ipv6Addr>,
+    pub cname: Option<String>,
+    pub caa: Vec<CaaRecord>,
+    pub tlsa: Vec<TlsaRecord>,
+}
+
+/// Build the resolver once per run (reading system resolver config a single
+/// time) so it can be shared across every worker instead of being rebuilt
+/// per domain.
+pub fn new_resolver() -> anyhow::Result<TokioAsyncResolver> {
+    Ok(TokioAsyncResolver::tokio_from_system_conf()?)
+}
+
+/// Resolve A/AAAA/CNAME/CAA/TLSA records for `host` ahead of connecting to
+/// it, bounded by `dns_timeout` so a black-holed or unresolvable host can't
+/// stall its worker indefinitely. Only a failure to resolve any address at
+/// all is treated as fatal; CAA and TLSA are best-effort since most domains
+/// won't publish either.
+pub async fn resolve(
+    resolver: &TokioAsyncResolver,
+    host: &str,
+    dns_timeout: Duration,
+) -> anyhow::Result<DnsRecords> {
+    match tokio::time::timeout(dns_timeout, resolve_inner(resolver, host)).await {
+        Ok(result) => result,
+        Err(_) => anyhow::bail!("DNS resolution for {host} timed out"),
+    }
+}
+
+async fn resolve_inner(resolver: &TokioAsyncResolver, host: &str) -> anyhow::Result<DnsRecords> {
+    let mut records = DnsRecords::default();
+
+    let lookup = resolver.lookup_ip(host).await?;
+    for addr in lookup.iter() {
+        match addr {
+            std::net::IpAddr::V4(v4) => records.a.push(v4),
+            std::net::IpAddr::V6(v6) => records.aaaa.push(v6),
+        }
+    }
+
+    if records.a.is_empty() && records.aaaa.is_empty() {
+        anyhow::bail!("no A/AAAA records for {host}");
+    }
+
+    if let Ok(lookup) = resolver.lookup(host, RecordType::CNAME).await {
+        records.cname = lookup
+            .record_iter()
+            .find_map(|r| r.data().and_then(RData::as_cname).map(|n| n.to_string()));
+    }
+
+    if let Ok(lookup) = resolver.lookup(host, RecordType::CAA).await {
+        records.caa = lookup
+            .record_iter()
+            .filter_map(|r| {
+                let caa = r.data()?.as_caa()?;
+                Some(CaaRecord {
+                    critical: caa.issuer_critical(),
+                    tag: caa.tag().to_string(),
+                    value: format!("{:?}", caa.value()),
+                })
+            })
+            .collect();
+    }
+
+    // TLSA records live at _<port>._tcp.<host>, per RFC 6698.
+    let tlsa_name = format!("_443._tcp.{host}");
+    if let Ok(lookup) = resolver.lookup(tlsa_name, RecordType::TLSA).await {
+        records.tlsa = lookup
+            .record_iter()
+            .filter_map(|r| r.data().and_then(RData::as_tlsa).map(tlsa_to_record))
+            .collect();
+    }
+
+    Ok(records)
+}
+
+fn tlsa_to_record(tlsa: &TLSA) -> TlsaRecord {
+    TlsaRecord {
+        cert_usage: u8::from(tlsa.cert_usage()),
+        selector: u8::from(tlsa.selector()),
+        matching_type: u8::from(tlsa.matching()),
+        association_data: tlsa.cert_data().to_vec(),
+    }
+}
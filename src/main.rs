@@ -1,23 +1,39 @@
-use std::{env, error::Error, fmt::Display, net::TcpStream as StdTcpStream};
+mod backend;
+mod cert;
+mod dane;
+mod dns;
+mod result;
+#[cfg(test)]
+mod test_fixtures;
+
+use std::{
+    env,
+    fmt::Display,
+    time::{Duration, Instant},
+};
 
 use anyhow::Result;
 use futures_util::future::join_all;
-use openssl::ssl::{Error as SslError, SslConnector, SslMethod};
+use hickory_resolver::TokioAsyncResolver;
 use reqwest::{Client, Url};
 use serde::{Deserialize, Serialize};
-use tokio::{net::TcpStream, sync::mpsc};
-use tokio_native_tls::{native_tls, TlsConnector};
+use tokio::sync::mpsc;
+
+use backend::{Backend, ConnectOutcome};
+use cert::CertWarning;
+use dane::DaneOutcome;
+use result::{CheckResult, OutputFormat};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-struct Domain {
+pub struct Domain {
     #[serde(rename = "Rank")]
-    rank: usize,
+    pub(crate) rank: usize,
 
     #[serde(rename = "Domain")]
-    domain: String,
+    pub(crate) domain: String,
 
     #[serde(rename = "Open Page Rank")]
-    page_rank: f64,
+    pub(crate) page_rank: f64,
 }
 
 impl Display for Domain {
@@ -33,17 +49,117 @@ impl Display for Domain {
 const WORKERS: usize = 50;
 const CHANNEL_BUFFER: usize = 10;
 
+const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 5;
+const DEFAULT_HANDSHAKE_TIMEOUT_SECS: u64 = 10;
+const DEFAULT_DNS_TIMEOUT_SECS: u64 = 5;
+const DEFAULT_BACKEND: Backend = Backend::OpenSsl;
+const DEFAULT_EXPIRY_WARN_DAYS: i32 = 30;
+const DEFAULT_OUTPUT_FORMAT: OutputFormat = OutputFormat::Text;
+
+/// Command line configuration for a run.
+struct Config {
+    file_name: Option<String>,
+    connect_timeout: Duration,
+    handshake_timeout: Duration,
+    dns_timeout: Duration,
+    backend: Backend,
+    expiry_warn_days: i32,
+    output_format: OutputFormat,
+    classify: bool,
+}
+
+fn parse_args() -> Config {
+    let mut file_name = None;
+    let mut connect_timeout = Duration::from_secs(DEFAULT_CONNECT_TIMEOUT_SECS);
+    let mut handshake_timeout = Duration::from_secs(DEFAULT_HANDSHAKE_TIMEOUT_SECS);
+    let mut dns_timeout = Duration::from_secs(DEFAULT_DNS_TIMEOUT_SECS);
+    let mut backend = DEFAULT_BACKEND;
+    let mut expiry_warn_days = DEFAULT_EXPIRY_WARN_DAYS;
+    let mut output_format = DEFAULT_OUTPUT_FORMAT;
+    let mut classify = false;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--connect-timeout" => {
+                if let Some(secs) = args.next().and_then(|v| v.parse().ok()) {
+                    connect_timeout = Duration::from_secs(secs);
+                }
+            }
+            "--handshake-timeout" => {
+                if let Some(secs) = args.next().and_then(|v| v.parse().ok()) {
+                    handshake_timeout = Duration::from_secs(secs);
+                }
+            }
+            "--dns-timeout" => {
+                if let Some(secs) = args.next().and_then(|v| v.parse().ok()) {
+                    dns_timeout = Duration::from_secs(secs);
+                }
+            }
+            "--backend" => {
+                if let Some(chosen) = args.next().and_then(|v| v.parse().ok()) {
+                    backend = chosen;
+                }
+            }
+            "--expiry-warn-days" => {
+                if let Some(days) = args.next().and_then(|v| v.parse().ok()) {
+                    expiry_warn_days = days;
+                }
+            }
+            "--output" => {
+                if let Some(chosen) = args.next().and_then(|v| v.parse().ok()) {
+                    output_format = chosen;
+                }
+            }
+            "--classify" => classify = true,
+            other => file_name = Some(other.to_string()),
+        }
+    }
+
+    Config {
+        file_name,
+        connect_timeout,
+        handshake_timeout,
+        dns_timeout,
+        backend,
+        expiry_warn_days,
+        output_format,
+        classify,
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
+    let config = parse_args();
+    let resolver = dns::new_resolver()?;
+
+    let (results_tx, results_rx) = mpsc::channel::<CheckResult>(CHANNEL_BUFFER);
+    let reporter = tokio::spawn(result::report(results_rx, config.output_format));
+
     let mut channels = vec![];
     channels.resize_with(WORKERS, || mpsc::channel::<Domain>(CHANNEL_BUFFER));
     let workers = channels
         .into_iter()
-        .enumerate()
-        .map(|(index, (tx, rx))| (tx, tokio::spawn(worker_openssl(index, rx))))
+        .map(|(tx, rx)| {
+            (
+                tx,
+                tokio::spawn(worker(
+                    rx,
+                    results_tx.clone(),
+                    resolver.clone(),
+                    config.backend,
+                    config.connect_timeout,
+                    config.handshake_timeout,
+                    config.dns_timeout,
+                    config.expiry_warn_days,
+                    config.classify,
+                )),
+            )
+        })
         .collect::<Vec<_>>();
+    drop(results_tx);
 
-    if let Some(file_name) = env::args().nth(1) {
+    if let Some(file_name) = &config.file_name {
         let mut rdr = csv::Reader::from_path(file_name)?;
 
         for (index, result) in rdr.deserialize().enumerate() {
@@ -54,54 +170,151 @@ async fn main() -> Result<()> {
         usage();
     }
 
-    // wait for the workers to do their thing
+    // dropping each worker's `tx` here (via the `(_, w)` destructure) closes
+    // its domain channel so the worker loop ends; once every worker has
+    // finished, `results_tx` has no more clones alive and the reporter's
+    // channel closes too
     let _ = join_all(workers.into_iter().map(|(_, w)| w)).await;
+    reporter.await??;
 
     Ok(())
 }
 
 fn usage() {
-    eprintln!("Usage:\n  check-domains <<domains.csv>>");
+    eprintln!(
+        "Usage:\n  check-domains <<domains.csv>> [--backend openssl|native-tls|rustls] \
+         [--connect-timeout secs] [--handshake-timeout secs] [--dns-timeout secs] \
+         [--expiry-warn-days days] [--output text|json|csv] [--classify]"
+    );
 }
 
-async fn worker_openssl(index: usize, mut rx: mpsc::Receiver<Domain>) -> Result<()> {
-    let connector = SslConnector::builder(SslMethod::tls_client())?.build();
-
+async fn worker(
+    mut rx: mpsc::Receiver<Domain>,
+    results_tx: mpsc::Sender<CheckResult>,
+    resolver: TokioAsyncResolver,
+    backend: Backend,
+    connect_timeout: Duration,
+    handshake_timeout: Duration,
+    dns_timeout: Duration,
+    expiry_warn_days: i32,
+    classify: bool,
+) -> Result<()> {
     while let Some(domain) = rx.recv().await {
-        let host = format!("{}:443", domain.domain);
-        let stream = StdTcpStream::connect(&host)?;
-        match connector.connect(&domain.domain, stream) {
-            Ok(_) => println!("[{index}] ok {}", domain),
+        let started = Instant::now();
+
+        let records = match dns::resolve(&resolver, &domain.domain, dns_timeout).await {
+            Ok(records) => records,
             Err(err) => {
-                if let Some(src) = err.source().and_then(|e| e.downcast_ref::<SslError>()) {
-                    if src.code().as_raw() == 5 {
-                        eprintln!("BLOCKED! domain {}", domain);
-                    }
-                }
+                let result = CheckResult::from_connect_outcome(
+                    domain.clone(),
+                    &ConnectOutcome::DnsFail(err.to_string()),
+                    started.elapsed().as_millis() as u64,
+                );
+                results_tx.send(result).await?;
+                continue;
             }
-        }
+        };
+
+        // Pin the address the initial attempt actually connects to, so the
+        // DANE/TLSA check below and any `--classify` re-probe are guaranteed
+        // to be about the same server `records` describes, rather than each
+        // independently re-resolving the host and possibly landing on a
+        // different edge.
+        let addr = records
+            .a
+            .first()
+            .map(|ip| ip.to_string())
+            .or_else(|| records.aaaa.first().map(|ip| ip.to_string()));
+        let Some(addr) = addr else {
+            let result = CheckResult::from_connect_outcome(
+                domain.clone(),
+                &ConnectOutcome::DnsFail(format!("no A/AAAA records for {}", domain.domain)),
+                started.elapsed().as_millis() as u64,
+            );
+            results_tx.send(result).await?;
+            continue;
+        };
+
+        let outcome = backend::check_domain_at(
+            backend,
+            &addr,
+            &domain.domain,
+            connect_timeout,
+            handshake_timeout,
+        )
+        .await;
+
+        let outcome = match outcome {
+            ConnectOutcome::HandshakeReset if classify => {
+                let kind = backend::classify_reset(
+                    backend,
+                    &addr,
+                    &domain.domain,
+                    connect_timeout,
+                    handshake_timeout,
+                )
+                .await;
+                ConnectOutcome::Blocked(kind)
+            }
+            other => other,
+        };
+
+        let mut outcome = match outcome {
+            ConnectOutcome::Ok {
+                leaf_cert_der: Some(der),
+            } if !records.tlsa.is_empty() => match dane::verify(&records.tlsa, &der) {
+                DaneOutcome::Fail => ConnectOutcome::DaneFail,
+                DaneOutcome::Verified | DaneOutcome::NotApplicable => ConnectOutcome::Ok {
+                    leaf_cert_der: Some(der),
+                },
+            },
+            other => other,
+        };
+
+        let cert_warnings = match &mut outcome {
+            ConnectOutcome::Ok { leaf_cert_der } => {
+                inspect_cert(&domain, leaf_cert_der.take(), expiry_warn_days)
+            }
+            _ => None,
+        };
+
+        let mut result = CheckResult::from_connect_outcome(
+            domain.clone(),
+            &outcome,
+            started.elapsed().as_millis() as u64,
+        );
+        result.detail = result.detail.or(cert_warnings);
+
+        results_tx.send(result).await?;
     }
 
     Ok(())
 }
 
-async fn _worker_tls(index: usize, mut rx: mpsc::Receiver<Domain>) -> Result<()> {
-    while let Some(domain) = rx.recv().await {
-        let host = format!("{}:443", domain.domain);
-        let stream = TcpStream::connect(&host).await?;
-        let connector = TlsConnector::from(native_tls::TlsConnector::new()?);
-        match connector
-            .connect(&domain.domain, stream)
-            .await
-            .map_err(|e| e.to_string())
-        {
-            Ok(_) => println!("[{index}] ok {}", domain),
-            Err(err) if err.contains("EOF") => eprintln!("[{index}] BLOCKED! domain {}", domain),
-            Err(_) => {}
-        }
-    }
+/// Parse the leaf cert and turn any warnings into a single detail string.
+fn inspect_cert(
+    domain: &Domain,
+    leaf_cert_der: Option<Vec<u8>>,
+    expiry_warn_days: i32,
+) -> Option<String> {
+    let der = leaf_cert_der?;
 
-    Ok(())
+    match cert::inspect(&der, &domain.domain, expiry_warn_days) {
+        Ok((_, warnings)) if !warnings.is_empty() => Some(
+            warnings
+                .into_iter()
+                .map(|w| match w {
+                    CertWarning::ExpiringSoon { days_left } => {
+                        format!("expires in {days_left} days")
+                    }
+                    CertWarning::SanMismatch => "SNI host not covered by any SAN".to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join("; "),
+        ),
+        Ok(_) => None,
+        Err(err) => Some(format!("failed to parse cert: {err}")),
+    }
 }
 
 async fn _worker_http(index: usize, mut rx: mpsc::Receiver<Domain>) -> Result<()> {
@@ -0,0 +1,166 @@
+use std::io::Write;
+
+use serde::Serialize;
+
+use crate::{
+    backend::{BlockKind, ConnectOutcome},
+    Domain,
+};
+
+/// The category a single domain's check landed in, independent of which
+/// backend or detail text produced it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Outcome {
+    Ok,
+    Blocked,
+    /// Only produced under `--classify`: the real SNI reset while a benign
+    /// control SNI to the same IP completed a clean handshake, so the block
+    /// is SNI-triggered.
+    BlockedSni,
+    /// Only produced under `--classify`: every re-probe result other than
+    /// `BlockedSni`'s, i.e. both SNIs reset, or the benign probe failed in
+    /// some other way (cert error, timeout, refused) and so can't clear the
+    /// IP.
+    BlockedIp,
+    Timeout,
+    CertInvalid,
+    DnsFail,
+    ConnectRefused,
+    DaneFail,
+}
+
+impl std::fmt::Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Outcome::Ok => "OK",
+            Outcome::Blocked => "BLOCKED",
+            Outcome::BlockedSni => "BLOCKED_SNI",
+            Outcome::BlockedIp => "BLOCKED_IP",
+            Outcome::Timeout => "TIMEOUT",
+            Outcome::CertInvalid => "CERT_INVALID",
+            Outcome::DnsFail => "DNS_FAIL",
+            Outcome::ConnectRefused => "CONNECT_REFUSED",
+            Outcome::DaneFail => "DANE_FAIL",
+        };
+        f.write_str(s)
+    }
+}
+
+/// The outcome of checking one domain, in a shape meant to be collected and
+/// post-processed rather than scraped off stdout/stderr.
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    pub domain: Domain,
+    pub outcome: Outcome,
+    pub detail: Option<String>,
+    pub elapsed_ms: u64,
+}
+
+impl CheckResult {
+    pub fn from_connect_outcome(domain: Domain, outcome: &ConnectOutcome, elapsed_ms: u64) -> Self {
+        let (outcome, detail) = match outcome {
+            ConnectOutcome::Ok { .. } => (Outcome::Ok, None),
+            ConnectOutcome::HandshakeReset => (Outcome::Blocked, None),
+            ConnectOutcome::Blocked(BlockKind::Sni) => (Outcome::BlockedSni, None),
+            ConnectOutcome::Blocked(BlockKind::Ip) => (Outcome::BlockedIp, None),
+            ConnectOutcome::CertInvalid(reason) => (Outcome::CertInvalid, Some(reason.clone())),
+            ConnectOutcome::ConnectRefused(reason) => {
+                (Outcome::ConnectRefused, Some(reason.clone()))
+            }
+            ConnectOutcome::Timeout => (Outcome::Timeout, None),
+            ConnectOutcome::DnsFail(reason) => (Outcome::DnsFail, Some(reason.clone())),
+            ConnectOutcome::DaneFail => (Outcome::DaneFail, None),
+        };
+
+        CheckResult {
+            domain,
+            outcome,
+            detail,
+            elapsed_ms,
+        }
+    }
+}
+
+/// The supported `--output` formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    Csv,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            other => Err(format!("unknown output format: {other}")),
+        }
+    }
+}
+
+/// A row written out by the `csv` format: the same columns the input CSV
+/// carried, plus the outcome, its detail and the elapsed time.
+#[derive(Serialize)]
+struct CsvRow<'a> {
+    #[serde(rename = "Rank")]
+    rank: usize,
+    #[serde(rename = "Domain")]
+    domain: &'a str,
+    #[serde(rename = "Open Page Rank")]
+    page_rank: f64,
+    #[serde(rename = "Outcome")]
+    outcome: String,
+    #[serde(rename = "Detail")]
+    detail: &'a str,
+    #[serde(rename = "ElapsedMs")]
+    elapsed_ms: u64,
+}
+
+/// Drain `results` and write each one to stdout in `format`, as they arrive.
+pub async fn report(
+    mut results: tokio::sync::mpsc::Receiver<CheckResult>,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    match format {
+        OutputFormat::Text => {
+            while let Some(result) = results.recv().await {
+                match &result.detail {
+                    Some(detail) => println!(
+                        "{} {} ({}, {}ms)",
+                        result.outcome, result.domain, detail, result.elapsed_ms
+                    ),
+                    None => println!(
+                        "{} {} ({}ms)",
+                        result.outcome, result.domain, result.elapsed_ms
+                    ),
+                }
+            }
+        }
+        OutputFormat::Json => {
+            while let Some(result) = results.recv().await {
+                println!("{}", serde_json::to_string(&result)?);
+            }
+        }
+        OutputFormat::Csv => {
+            let mut wtr = csv::Writer::from_writer(std::io::stdout());
+            while let Some(result) = results.recv().await {
+                wtr.serialize(CsvRow {
+                    rank: result.domain.rank,
+                    domain: &result.domain.domain,
+                    page_rank: result.domain.page_rank,
+                    outcome: result.outcome.to_string(),
+                    detail: result.detail.as_deref().unwrap_or(""),
+                    elapsed_ms: result.elapsed_ms,
+                })?;
+            }
+            wtr.flush()?;
+        }
+    }
+
+    std::io::stdout().flush()?;
+    Ok(())
+}
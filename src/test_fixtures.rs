@@ -0,0 +1,57 @@
+//! Shared DER-encoded test certificates for unit tests across modules.
+
+/// A throwaway self-signed certificate with no SAN extension and subject CN
+/// "example.com", used to exercise both DANE's selector/matching-type
+/// comparisons and `cert::inspect`'s legacy CN-only fallback. Its own
+/// validity/trust doesn't matter to either caller.
+pub(crate) const TEST_CERT_DER: &[u8] = &[
+    48, 130, 3, 13, 48, 130, 1, 245, 160, 3, 2, 1, 2, 2, 20, 101, 92, 152, 242, 222, 240, 202, 123,
+    50, 12, 228, 78, 206, 106, 129, 90, 187, 255, 85, 212, 48, 13, 6, 9, 42, 134, 72, 134, 247, 13,
+    1, 1, 11, 5, 0, 48, 22, 49, 20, 48, 18, 6, 3, 85, 4, 3, 12, 11, 101, 120, 97, 109, 112, 108,
+    101, 46, 99, 111, 109, 48, 30, 23, 13, 50, 54, 48, 55, 50, 55, 49, 56, 51, 49, 48, 50, 90, 23,
+    13, 50, 55, 48, 55, 50, 55, 49, 56, 51, 49, 48, 50, 90, 48, 22, 49, 20, 48, 18, 6, 3, 85, 4, 3,
+    12, 11, 101, 120, 97, 109, 112, 108, 101, 46, 99, 111, 109, 48, 130, 1, 34, 48, 13, 6, 9, 42,
+    134, 72, 134, 247, 13, 1, 1, 1, 5, 0, 3, 130, 1, 15, 0, 48, 130, 1, 10, 2, 130, 1, 1, 0, 194,
+    53, 115, 4, 145, 189, 126, 210, 10, 183, 82, 218, 48, 4, 111, 174, 221, 109, 134, 217, 127, 75,
+    20, 82, 31, 49, 71, 208, 243, 214, 161, 177, 52, 43, 162, 186, 155, 156, 253, 190, 95, 180,
+    128, 1, 207, 177, 206, 139, 204, 36, 172, 177, 245, 228, 105, 219, 130, 103, 251, 31, 161, 220,
+    128, 194, 197, 73, 114, 59, 11, 52, 114, 9, 8, 144, 116, 97, 216, 149, 103, 200, 190, 248, 176,
+    115, 31, 5, 60, 117, 196, 235, 21, 178, 132, 2, 84, 93, 229, 222, 192, 114, 223, 43, 254, 253,
+    182, 67, 153, 147, 51, 80, 190, 20, 241, 240, 114, 145, 105, 209, 134, 136, 195, 82, 35, 188,
+    115, 133, 234, 224, 34, 113, 73, 103, 40, 240, 90, 56, 10, 72, 133, 9, 127, 149, 71, 125, 137,
+    62, 12, 148, 154, 168, 28, 226, 21, 64, 122, 154, 22, 3, 135, 159, 82, 92, 217, 220, 117, 227,
+    147, 196, 129, 254, 24, 216, 203, 147, 149, 109, 186, 39, 12, 142, 218, 233, 54, 6, 3, 51, 43,
+    0, 189, 1, 17, 182, 10, 255, 117, 235, 97, 25, 93, 120, 228, 232, 44, 28, 174, 244, 236, 83,
+    124, 124, 199, 166, 100, 124, 211, 126, 108, 188, 83, 116, 88, 98, 24, 189, 179, 249, 107, 172,
+    176, 69, 128, 232, 16, 182, 232, 54, 17, 23, 188, 171, 2, 42, 27, 5, 71, 80, 110, 29, 161, 235,
+    129, 216, 67, 102, 61, 181, 2, 3, 1, 0, 1, 163, 83, 48, 81, 48, 29, 6, 3, 85, 29, 14, 4, 22, 4,
+    20, 178, 220, 255, 252, 152, 195, 242, 194, 61, 127, 37, 105, 69, 102, 31, 235, 39, 232, 35,
+    208, 48, 31, 6, 3, 85, 29, 35, 4, 24, 48, 22, 128, 20, 178, 220, 255, 252, 152, 195, 242, 194,
+    61, 127, 37, 105, 69, 102, 31, 235, 39, 232, 35, 208, 48, 15, 6, 3, 85, 29, 19, 1, 1, 255, 4,
+    5, 48, 3, 1, 1, 255, 48, 13, 6, 9, 42, 134, 72, 134, 247, 13, 1, 1, 11, 5, 0, 3, 130, 1, 1, 0,
+    126, 240, 104, 249, 71, 53, 24, 64, 20, 31, 113, 107, 236, 213, 81, 254, 121, 71, 95, 47, 150,
+    82, 93, 11, 229, 15, 20, 65, 123, 84, 13, 66, 138, 41, 64, 27, 214, 194, 125, 194, 58, 156,
+    125, 182, 110, 85, 92, 102, 177, 34, 176, 17, 213, 142, 254, 93, 46, 176, 78, 28, 122, 120,
+    145, 156, 101, 173, 184, 80, 70, 117, 158, 200, 242, 177, 40, 111, 240, 205, 250, 215, 99, 180,
+    175, 54, 26, 159, 135, 255, 227, 107, 243, 90, 218, 45, 103, 136, 14, 7, 194, 29, 79, 57, 11,
+    233, 254, 8, 167, 185, 220, 188, 168, 204, 159, 132, 44, 136, 79, 254, 128, 220, 90, 80, 240,
+    145, 51, 136, 153, 67, 110, 186, 174, 137, 111, 2, 33, 194, 5, 224, 5, 81, 55, 229, 127, 56,
+    143, 27, 213, 40, 234, 36, 91, 233, 232, 113, 145, 81, 228, 92, 246, 147, 83, 155, 195, 106,
+    72, 27, 130, 55, 151, 251, 125, 254, 229, 216, 132, 38, 71, 243, 190, 139, 65, 218, 38, 196,
+    120, 207, 26, 228, 251, 213, 73, 87, 56, 208, 68, 7, 198, 36, 197, 148, 156, 159, 207, 124, 42,
+    167, 172, 163, 136, 2, 146, 136, 21, 45, 39, 86, 205, 173, 80, 30, 76, 32, 162, 171, 14, 168,
+    70, 179, 79, 66, 217, 197, 245, 38, 63, 14, 187, 163, 238, 68, 92, 250, 98, 194, 17, 238, 2,
+    65, 45, 99, 106, 220, 24, 144, 130, 179,
+];
+
+/// SHA-256 of the whole `TEST_CERT_DER`.
+pub(crate) const TEST_CERT_SHA256: [u8; 32] = [
+    62, 44, 236, 5, 115, 33, 205, 25, 50, 170, 227, 26, 121, 24, 104, 144, 245, 46, 108, 4, 248,
+    92, 114, 103, 61, 86, 110, 158, 160, 218, 75, 119,
+];
+
+/// SHA-256 of `TEST_CERT_DER`'s SubjectPublicKeyInfo.
+pub(crate) const TEST_SPKI_SHA256: [u8; 32] = [
+    224, 186, 209, 18, 221, 224, 79, 12, 250, 48, 119, 169, 200, 112, 96, 193, 6, 233, 41, 229,
+    139, 254, 0, 233, 176, 224, 164, 174, 255, 27, 195, 85,
+];